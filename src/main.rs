@@ -1,8 +1,10 @@
 use std::env;
-use std::io::Write;
+use std::io::{BufRead, Write};
 
 use getopts::Options;
-use std::collections::BTreeMap;
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use msvc_demangler;
 
 use pdb::{FallibleIterator, SymbolData, PDB, LineProgram, AddressMap};
@@ -19,6 +21,221 @@ pub struct LineInfo {
     pub file: String,
     /// Absolute line number starting at 1. Zero means no line number.
     pub line: u64,
+    /// Column of the start of the covered source range. Zero means no column.
+    pub column: u32,
+    /// Line number of the end of the covered source range.
+    pub line_end: u64,
+    /// Whether the record covers a statement rather than an expression.
+    pub is_statement: bool,
+}
+
+/// A caller-supplied code range, e.g. for JIT-compiled code, that lookups report alongside the
+/// PDB's own symbols.
+struct ExtraRange {
+    start: u32,
+    end: u32,
+    name: String,
+    location: Option<String>,
+}
+
+/// Parses extra ranges with one tab-separated `START END NAME [FILE:LINE]` record per line,
+/// addresses in hex. Blank lines are skipped. Also returns the numbers, starting at 1, of the
+/// lines that don't parse.
+fn parse_extra_ranges(contents: &str) -> (Vec<ExtraRange>, Vec<usize>) {
+    let parse_hex = |s: &str| u32::from_str_radix(s.trim().trim_start_matches("0x"), 16).ok();
+    let parse_line = |line: &str| {
+        let mut fields = line.split('\t');
+        Some(ExtraRange {
+            start: parse_hex(fields.next()?)?,
+            end: parse_hex(fields.next()?)?,
+            name: fields.next()?.to_string(),
+            location: fields.next().map(str::to_string),
+        })
+    };
+
+    let mut ranges = Vec::new();
+    let mut bad_lines = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some(range) => ranges.push(range),
+            None => bad_lines.push(i + 1),
+        }
+    }
+    (ranges, bad_lines)
+}
+
+/// Reads extra ranges from a file in the format `parse_extra_ranges` takes. Lines that don't
+/// parse are reported on stderr and skipped.
+fn read_extra_ranges(path: &str) -> std::io::Result<Vec<ExtraRange>> {
+    let (ranges, bad_lines) = parse_extra_ranges(&std::fs::read_to_string(path)?);
+    for line in bad_lines {
+        writeln!(&mut std::io::stderr(), "{}:{}: invalid range", path, line).expect("stderr write");
+    }
+    Ok(ranges)
+}
+
+/// Settings controlling what `dump_pdb` looks for and prints.
+#[derive(Default)]
+struct DumpOptions {
+    /// Treat procedures with `len == 0` as extending up to the next procedure's start.
+    zero_length: bool,
+    /// Print every line record of the matching procedure instead of the one covering the target.
+    all_lines: bool,
+    /// Print every inline site of the matching procedure, not just the ones covering the target.
+    inline_tree: bool,
+    /// Skip inline sites entirely and only report the outer procedure.
+    no_inlines: bool,
+    /// Only look at modules whose name matches this glob.
+    module_filter: Option<String>,
+    /// Ranges to report in addition to the PDB's symbols.
+    extra_ranges: Vec<ExtraRange>,
+    /// The target is the last byte of a call, one before a return address, so call site records
+    /// match when their call instruction contains it rather than starts at it.
+    return_address: bool,
+}
+
+/// Kinds of symbol records that the `pdb` crate doesn't parse.
+const S_ANNOTATION: u16 = 0x1019;
+const S_CALLSITEINFO: u16 = 0x1139;
+const S_HEAPALLOCSITE: u16 = 0x115e;
+const S_CALLEES: u16 = 0x115a;
+const S_CALLERS: u16 = 0x115b;
+const S_INLINEES: u16 = 0x1168;
+const S_ARMSWITCHTABLE: u16 = 0x1159;
+
+/// The longest x86 and x64 instruction, which bounds how far before a return address its call
+/// can start.
+const MAX_INSTRUCTION_LEN: u32 = 15;
+
+/// Jump table metadata from an S_ARMSWITCHTABLE record.
+struct SwitchTable {
+    base: pdb::PdbInternalSectionOffset,
+    switch_type: u16,
+    branch: pdb::PdbInternalSectionOffset,
+    table: pdb::PdbInternalSectionOffset,
+    entries: u32,
+}
+
+fn raw_u16(data: &[u8], pos: usize) -> Option<u16> {
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn raw_u32(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Reads the offset/section pair that most code symbols store right after their kind.
+fn raw_offset(data: &[u8]) -> Option<pdb::PdbInternalSectionOffset> {
+    Some(pdb::PdbInternalSectionOffset {
+        offset: raw_u32(data, 2)?,
+        section: raw_u16(data, 6)?,
+    })
+}
+
+/// Splits a raw S_ANNOTATION record into its code offset and annotation strings.
+fn parse_annotation(data: &[u8]) -> Option<(pdb::PdbInternalSectionOffset, Vec<String>)> {
+    let offset = raw_offset(data)?;
+    let count = raw_u16(data, 8)?;
+    let strings = data[10..]
+        .split(|&b| b == 0)
+        .take(count.into())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect();
+    Some((offset, strings))
+}
+
+/// Reads the call instruction offset and the callee's function type from a raw
+/// S_CALLSITEINFO record.
+fn parse_call_site_info(data: &[u8]) -> Option<(pdb::PdbInternalSectionOffset, pdb::TypeIndex)> {
+    Some((raw_offset(data)?, pdb::TypeIndex(raw_u32(data, 10)?)))
+}
+
+/// Reads the call offset, call instruction length and allocated type from a raw
+/// S_HEAPALLOCSITE record.
+fn parse_heap_alloc_site(data: &[u8]) -> Option<(pdb::PdbInternalSectionOffset, u16, pdb::TypeIndex)> {
+    Some((raw_offset(data)?, raw_u16(data, 8)?, pdb::TypeIndex(raw_u32(data, 10)?)))
+}
+
+/// Reads the function indices and, where present, invocation counts from a raw S_CALLERS,
+/// S_CALLEES or S_INLINEES record. S_INLINEES records have no counts.
+fn parse_function_list(data: &[u8]) -> Option<Vec<(u32, Option<u32>)>> {
+    let count = raw_u32(data, 2)? as usize;
+    let counts_start = 6 + 4 * count;
+    (0..count)
+        .map(|i| Some((raw_u32(data, 6 + 4 * i)?, raw_u32(data, counts_start + 4 * i))))
+        .collect()
+}
+
+fn parse_switch_table(data: &[u8]) -> Option<SwitchTable> {
+    let section_offset = |offset_pos, section_pos| {
+        Some(pdb::PdbInternalSectionOffset {
+            offset: raw_u32(data, offset_pos)?,
+            section: raw_u16(data, section_pos)?,
+        })
+    };
+    Some(SwitchTable {
+        base: raw_offset(data)?,
+        switch_type: raw_u16(data, 8)?,
+        branch: section_offset(10, 18)?,
+        table: section_offset(14, 20)?,
+        entries: raw_u32(data, 22)?,
+    })
+}
+
+/// Returns the IPI stream, loading it on first use. PDBs without one give `None`, in which case
+/// inline sites are reported by index only. Other errors reading it are passed on.
+fn id_information<'s, 'a>(
+    pdb: &mut PDB<'s, File>,
+    ipi: &'a mut Option<Option<pdb::IdInformation<'s>>>,
+) -> pdb::Result<Option<&'a pdb::IdInformation<'s>>> {
+    if ipi.is_none() {
+        *ipi = Some(match pdb.id_information() {
+            Ok(stream) => Some(stream),
+            Err(pdb::Error::StreamNotFound(_)) => None,
+            Err(e) => return Err(e),
+        });
+    }
+    Ok(ipi.as_ref().and_then(Option::as_ref))
+}
+
+/// Maps the IPI index of every function and member function to its name, so inlinees can be
+/// named without a pass over the IPI stream each. Items that don't parse are left out.
+fn inlinee_names(ipi: &pdb::IdInformation<'_>) -> pdb::Result<HashMap<pdb::IdIndex, String>> {
+    let mut names = HashMap::new();
+    let mut items = ipi.iter();
+    while let Some(item) = items.next()? {
+        let name = match item.parse() {
+            Ok(pdb::IdData::Function(f)) => f.name,
+            Ok(pdb::IdData::MemberFunction(f)) => f.name,
+            _ => continue,
+        };
+        names.insert(item.index(), name.to_string().into_owned());
+    }
+    Ok(names)
+}
+
+/// Resolved file names of one module's line program, so that each file is only looked up in the
+/// string table once.
+type FileNameCache = HashMap<pdb::FileIndex, String>;
+
+fn file_name(
+    program: &LineProgram,
+    string_table: &pdb::StringTable,
+    file_names: &mut FileNameCache,
+    index: pdb::FileIndex,
+) -> pdb::Result<String> {
+    Ok(match file_names.entry(index) {
+        Entry::Occupied(e) => e.get().clone(),
+        Entry::Vacant(e) => {
+            let file_info = program.get_file_info(index)?;
+            e.insert(file_info.name.to_string_lossy(string_table)?.into_owned()).clone()
+        }
+    })
 }
 
 fn collect_lines<I>(
@@ -26,6 +243,7 @@ fn collect_lines<I>(
     program: &LineProgram,
     address_map: &AddressMap,
     string_table: &pdb::StringTable,
+    file_names: &mut FileNameCache,
 ) -> Result<Vec<LineInfo>, pdb::Error>
 where
     I: FallibleIterator<Item = pdb::LineInfo, Error = pdb::Error>
@@ -38,21 +256,56 @@ where
             None => continue,
         };
 
-        let file_info = program.get_file_info(line_info.file_index)?;
+        let file = file_name(program, string_table, file_names, line_info.file_index)?;
         lines.push(LineInfo {
             address: rva,
             size: line_info.length.map(u64::from),
-            file: file_info.name.to_string_lossy(string_table).unwrap().to_string(),
+            file,
             line: line_info.line_start.into(),
+            column: line_info.column_start.unwrap_or(0),
+            line_end: line_info.line_end.into(),
+            is_statement: line_info.kind == pdb::LineInfoKind::Statement,
         });
     }
 
     Ok(lines)
 }
 
+/// Sorts `lines` by address and gives each record without a length, which is every module line
+/// record, the size up to the next record, or up to `end` for the last one.
+fn infer_line_sizes(lines: &mut [LineInfo], end: u64) {
+    lines.sort_by_key(|l| l.address);
+    let next_starts: Vec<u64> = lines.iter().skip(1).map(|l| l.address).chain(Some(end)).collect();
+    for (line, next_start) in lines.iter_mut().zip(next_starts) {
+        if line.size.is_none() {
+            line.size = Some(next_start.min(end).saturating_sub(line.address));
+        }
+    }
+}
+
+
 
+/// Control Flow Guard helpers injected by the compiler and linker. They only have public
+/// symbols, under a few decorated spellings, so they're reported under one canonical name. The
+/// `_fptr` publics next to them are the data pointers through which the helpers are called, not
+/// code, so they keep their own names.
+const GUARD_HELPERS: &[&str] = &[
+    "guard_check_icall",
+    "guard_dispatch_icall",
+    "guard_xfg_check_icall",
+    "guard_xfg_dispatch_icall",
+    "guard_xfg_table_dispatch_icall",
+    "guard_icall_checks_enforced",
+    "guard_ss_verify_failure",
+];
+
+fn guard_helper_label(name: &str) -> Option<&'static str> {
+    let name = name.trim_start_matches('_');
+    let name = name.strip_suffix("_nop").unwrap_or(name);
+    GUARD_HELPERS.iter().copied().find(|&helper| helper == name)
+}
 
-fn print_nearest_symbol(mut symbols: pdb::SymbolIter<'_>, address_map: &pdb::AddressMap, target: u32) -> pdb::Result<()> {
+fn print_nearest_symbol<W: Write>(mut symbols: pdb::SymbolIter<'_>, address_map: &pdb::AddressMap, target: u32, out: &mut W) -> pdb::Result<()> {
 
     let mut nearest_symbol = None;
 
@@ -65,14 +318,14 @@ fn print_nearest_symbol(mut symbols: pdb::SymbolIter<'_>, address_map: &pdb::Add
 
                     Some(start) if start.0 <= target && target < start.0 + proc.len => {
                         let sign = if proc.global { "+" } else { "-" };
-                        println!("{} {} {:?} {}", sign, proc.name, proc.offset.to_rva(&address_map), proc.len);
+                        writeln!(out, "{} {} {:?} {}", sign, proc.name, proc.offset.to_rva(&address_map), proc.len)?;
                     }
                     Some(_) => {
                         //println!("{:?} {} {}", proc.offset.to_rva(&address_map), proc.name, proc.len);
                         //println!("{} {:?} {:?} {} {:?} {}", sign, symbol.index(), proc.type_index, proc.name, proc.offset.to_rva(&address_map), proc.len);
                     }
                     _ => {
-                        println!("error");
+                        writeln!(out, "error")?;
 
                     }
                 }
@@ -89,7 +342,7 @@ fn print_nearest_symbol(mut symbols: pdb::SymbolIter<'_>, address_map: &pdb::Add
                         }
                     }
                     _ => {
-                        println!("error");
+                        writeln!(out, "error")?;
 
                     }
                 }
@@ -99,29 +352,206 @@ fn print_nearest_symbol(mut symbols: pdb::SymbolIter<'_>, address_map: &pdb::Add
     }
 
     if let Some((off, sym)) = nearest_symbol {
-        let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
-        let result = msvc_demangler::demangle(&sym.name.to_string(), flags).unwrap();
-        println!("sym {:x} {}", off, result);
+        let name = sym.name.to_string();
+        if let Some(label) = guard_helper_label(&name) {
+            writeln!(out, "sym {:x} {} [synthetic]", off, label)?;
+        } else {
+            let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
+            let result = msvc_demangler::demangle(&name, flags).unwrap_or_else(|_| name.to_string());
+            writeln!(out, "sym {:x} {}", off, result)?;
+        }
     }
     
 
     Ok(())
 }
 
+/// Finds the section contribution containing `target` in `contributions`, which are sorted by
+/// section and offset.
+fn find_contribution(contributions: &[pdb::DBISectionContribution], target: pdb::PdbInternalSectionOffset) -> Option<&pdb::DBISectionContribution> {
+    let after = contributions.partition_point(|c| (c.offset.section, c.offset.offset) <= (target.section, target.offset));
+    let c = contributions.get(after.checked_sub(1)?)?;
+    if c.offset.section == target.section && target.offset - c.offset.offset < c.size {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+/// Whether a symbol at `start` may be reported as the nearest symbol before `target`: it has to be
+/// in the same section and, if a contribution contains the target, in that contribution, so that
+/// an address is never attributed to another object file's symbol.
+fn precedes_within(
+    start: pdb::PdbInternalSectionOffset,
+    target: pdb::PdbInternalSectionOffset,
+    contribution: Option<&pdb::DBISectionContribution>,
+) -> bool {
+    start.section == target.section
+        && start.offset <= target.offset
+        && contribution.is_none_or(|c| c.offset.offset <= start.offset)
+}
+
+/// Finds the last symbol at or before `target` in `by_offset`, which is sorted by section and
+/// offset. Every earlier symbol is further away, so if `precedes` rejects this one there is none.
+fn nearest_global<F>(by_offset: &[(pdb::PdbInternalSectionOffset, pdb::SymbolIndex)], target: pdb::PdbInternalSectionOffset, precedes: F) -> Option<pdb::SymbolIndex>
+where
+    F: Fn(pdb::PdbInternalSectionOffset) -> bool,
+{
+    let after = by_offset.partition_point(|(offset, _)| (offset.section, offset.offset) <= (target.section, target.offset));
+    let &(offset, index) = by_offset.get(after.checked_sub(1)?)?;
+    if precedes(offset) {
+        Some(index)
+    } else {
+        None
+    }
+}
+
 use std::fs::File;
-fn find_symbol(mut pdb: PDB<File>, target: u32) -> pdb::Result<()> {
-    let symbol_table = pdb.global_symbols()?;
-    let mut address_map = pdb.address_map()?;
 
-    println!("Global symbols:");
-    print_nearest_symbol(symbol_table.iter(), &mut address_map, target)?;
+/// Address range of a procedure and the index of the module it's in.
+struct ProcedureRange {
+    start: u32,
+    end: u32,
+    module: usize,
+}
 
-    println!("Module private symbols:");
+/// A PDB opened once for looking up any number of addresses.
+///
+/// Opening it reads every module stream once, one at a time, to index the procedures. After that a
+/// lookup only reads the modules with something at its address, the first time one is needed, and
+/// keeps them for later lookups. Global symbols are found by binary search over sorted indices
+/// instead of scanning the globals stream.
+struct LookupContext<'s> {
+    pdb: PDB<'s, File>,
+    address_map: AddressMap<'s>,
+    /// `None` if the PDB has no usable string table, in which case lookups fall back to symbols.
+    string_table: Option<pdb::StringTable<'s>>,
+    globals: pdb::SymbolTable<'s>,
+    dbi: pdb::DebugInformation<'s>,
+    module_names: Vec<String>,
+    /// The modules read so far, by module index.
+    module_infos: HashMap<usize, Option<pdb::ModuleInfo<'s>>>,
+    /// Every procedure with an RVA, sorted by start.
+    procs: Vec<ProcedureRange>,
+    /// The highest end among `procs[..=i]`, so the search for procedures covering an address can
+    /// stop as soon as nothing earlier reaches it.
+    proc_max_ends: Vec<u32>,
+    /// Section contributions, sorted by section and offset.
+    contributions: Vec<pdb::DBISectionContribution>,
+    /// Global data and public symbols, sorted by section and offset.
+    data: Vec<(pdb::PdbInternalSectionOffset, pdb::SymbolIndex)>,
+    publics: Vec<(pdb::PdbInternalSectionOffset, pdb::SymbolIndex)>,
+    /// Thread-local variables, sorted by their offset in the TLS block.
+    tls: Vec<(u32, pdb::SymbolIndex)>,
+    /// Only needed to name inlinees, so it's loaded the first time an inline site matches.
+    ipi: Option<Option<pdb::IdInformation<'s>>>,
+    /// Names from `inlinee_names`, built the first time `--inline-tree` prints an inline site.
+    inlinee_names: Option<HashMap<pdb::IdIndex, String>>,
+}
+
+fn open_lookup_context(filename: &str) -> pdb::Result<LookupContext<'static>> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let string_table = pdb.string_table().ok();
+    let globals = pdb.global_symbols()?;
     let dbi = pdb.debug_information()?;
+
+    let mut module_names = Vec::new();
+    let mut procs = Vec::new();
     let mut modules = dbi.modules()?;
     while let Some(module) = modules.next()? {
-        //println!("Module: {}", module.object_file_name());
+        let module_index = module_names.len();
+        module_names.push(module.module_name().into_owned());
         let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                if let Some(rva) = proc.offset.to_rva(&address_map) {
+                    procs.push(ProcedureRange { start: rva.0, end: rva.0 + proc.len, module: module_index });
+                }
+            }
+        }
+    }
+    procs.sort_by_key(|p| p.start);
+    let proc_max_ends = procs
+        .iter()
+        .scan(0, |max_end, p| {
+            *max_end = p.end.max(*max_end);
+            Some(*max_end)
+        })
+        .collect();
+
+    let mut contributions: Vec<_> = dbi.section_contributions()?.collect()?;
+    contributions.sort_by_key(|c| (c.offset.section, c.offset.offset));
+
+    let mut data = Vec::new();
+    let mut publics = Vec::new();
+    let mut tls = Vec::new();
+    let mut symbols = globals.iter();
+    while let Some(symbol) = symbols.next()? {
+        match symbol.parse() {
+            Ok(SymbolData::Data(d)) => data.push((d.offset, symbol.index())),
+            Ok(SymbolData::Public(p)) => publics.push((p.offset, symbol.index())),
+            Ok(SymbolData::ThreadStorage(t)) => tls.push((t.offset.offset, symbol.index())),
+            _ => {}
+        }
+    }
+    data.sort_by_key(|(offset, _)| (offset.section, offset.offset));
+    publics.sort_by_key(|(offset, _)| (offset.section, offset.offset));
+    tls.sort_by_key(|&(offset, _)| offset);
+
+    Ok(LookupContext {
+        pdb,
+        address_map,
+        string_table,
+        globals,
+        dbi,
+        module_names,
+        module_infos: HashMap::new(),
+        procs,
+        proc_max_ends,
+        contributions,
+        data,
+        publics,
+        tls,
+        ipi: None,
+        inlinee_names: None,
+    })
+}
+
+/// Returns the info of module `index`, reading it the first time it's needed.
+fn module_info<'a, 's>(
+    pdb: &mut PDB<'s, File>,
+    dbi: &pdb::DebugInformation<'s>,
+    module_infos: &'a mut HashMap<usize, Option<pdb::ModuleInfo<'s>>>,
+    index: usize,
+) -> pdb::Result<Option<&'a pdb::ModuleInfo<'s>>> {
+    let info = match module_infos.entry(index) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let info = match dbi.modules()?.nth(index)? {
+                Some(module) => pdb.module_info(&module)?,
+                None => None,
+            };
+            entry.insert(info)
+        }
+    };
+    Ok(info.as_ref())
+}
+
+fn find_symbol<W: Write>(context: &mut LookupContext<'_>, target: u32, out: &mut W) -> pdb::Result<()> {
+    writeln!(out, "Global symbols:")?;
+    print_nearest_symbol(context.globals.iter(), &context.address_map, target, out)?;
+
+    writeln!(out, "Module private symbols:")?;
+    let mut modules = context.dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        //println!("Module: {}", module.object_file_name());
+        let info = match context.pdb.module_info(&module)? {
             Some(info) => info,
             None => {
                 //println!("  no module info");
@@ -129,35 +559,160 @@ fn find_symbol(mut pdb: PDB<File>, target: u32) -> pdb::Result<()> {
             }
         };
 
-        print_nearest_symbol(info.symbols()?, &mut address_map, target)?;
+        print_nearest_symbol(info.symbols()?, &context.address_map, target, out)?;
     }
     Ok(())
 }
 
 
-fn dump_pdb(filename: &str, target: u32) -> pdb::Result<()> {
-    let file = std::fs::File::open(filename)?;
-    let mut pdb = PDB::open(file)?;
+/// Recognizes the names the compiler gives to exception handling funclets and returns the name of
+/// the function they belong to, along with the kind of funclet.
+///
+/// x86 uses `__ehhandler$F`, `__unwindfunclet$F$N` and `__catch$F$N` with the decorated parent
+/// name. x64 funclets are decorated as local names of their parent, like
+/// `?catch$2@?0??F@@YAXXZ@4HA`, which demangles to `` `F'::`1'::catch$2 ``; both spellings are
+/// recognized.
+fn funclet_parent(name: &str) -> Option<(&str, &'static str)> {
+    if let Some(rest) = name.strip_prefix("__ehhandler$") {
+        return Some((rest, "EH handler"));
+    }
+    for &(prefix, kind) in &[("__unwindfunclet$", "unwind funclet"), ("__catch$", "catch handler")] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            let parent = rest.rsplit_once('$').map_or(rest, |(parent, _)| parent);
+            return Some((parent, kind));
+        }
+    }
 
+    let x64_kind = |funclet: &str| match funclet.split('$').next() {
+        Some("catch") => Some("catch handler"),
+        Some("dtor") => Some("unwind funclet"),
+        Some("fin") => Some("finally handler"),
+        Some("filt") => Some("exception filter"),
+        _ => None,
+    };
 
-    let address_map = pdb.address_map()?;
-    let string_table = pdb.string_table();
-    let string_table = match string_table {
-        Ok(string_table) => string_table,
-        _ => {
-            println!("no string table using symbols");
-            return find_symbol(pdb, target);
+    if let Some(rest) = name.strip_prefix('?') {
+        // The funclet name, then the scope number and the decorated parent name, then the
+        // storage class after the last `@`.
+        let (funclet, rest) = rest.split_once("@?")?;
+        let kind = x64_kind(funclet)?;
+        let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || ('A'..='P').contains(&c) || c == '@');
+        let (parent, _) = rest.strip_prefix('?')?.rsplit_once('@')?;
+        return parent.starts_with('?').then_some((parent, kind));
+    }
+
+    let rest = name.strip_prefix('`')?;
+    let end = rest.find("'::`")?;
+    let kind = x64_kind(&rest[rest.rfind("'::")? + 3..])?;
+    Some((&rest[..end], kind))
+}
+
+/// Finds the procedure in `info` called by any of `names` and returns the line record at the
+/// lowest address in it.
+fn procedure_first_line(
+    info: &pdb::ModuleInfo<'_>,
+    program: &LineProgram,
+    address_map: &AddressMap,
+    string_table: &pdb::StringTable,
+    file_names: &mut FileNameCache,
+    names: &[&str],
+) -> pdb::Result<Option<LineInfo>> {
+    let mut symbols = info.symbols()?;
+    while let Some(symbol) = symbols.next()? {
+        if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+            if names.iter().any(|&name| proc.name.to_string() == name) {
+                let lines = collect_lines(program.lines_at_offset(proc.offset), program, address_map, string_table, file_names)?;
+                return Ok(lines.into_iter().min_by_key(|l| l.address));
+            }
         }
-    };
+    }
+    Ok(None)
+}
 
-    println!("Module private symbols:");
-    let dbi = pdb.debug_information()?;
-    let ipi = pdb.id_information()?;
+fn dump_pdb<W: Write>(context: &mut LookupContext<'_>, target: u32, options: &DumpOptions, out: &mut W) -> pdb::Result<()> {
+    for extra in &options.extra_ranges {
+        if extra.start <= target && target < extra.end {
+            match &extra.location {
+                Some(location) => writeln!(out, "extra {} {:x}+{:x} {}", extra.name, extra.start, target - extra.start, location)?,
+                None => writeln!(out, "extra {} {:x}+{:x}", extra.name, extra.start, target - extra.start)?,
+            }
+        }
+    }
 
-    let mut modules = dbi.modules()?;
-    while let Some(module) = modules.next()? {
 
-        let info = match pdb.module_info(&module)? {
+    if context.string_table.is_none() {
+        writeln!(out, "no string table using symbols")?;
+        return find_symbol(context, target, out);
+    }
+    let LookupContext { pdb, address_map, string_table, globals, dbi, module_names, module_infos, procs, proc_max_ends, contributions, data, publics, ipi, inlinee_names: names, .. } = context;
+    let string_table = string_table.as_ref().unwrap();
+    let module_filter = options.module_filter.as_deref();
+
+    writeln!(out, "Module private symbols:")?;
+
+    // Bounds for the fallbacks that report the nearest symbol before an address no procedure
+    // covers.
+    let target_offset = pdb::Rva(target).to_internal_offset(address_map);
+    let contribution = target_offset.and_then(|offset| find_contribution(contributions, offset));
+    let precedes_target = |start| target_offset.is_some_and(|target| precedes_within(start, target, contribution));
+
+    // Start of the last procedure at or before the target, and the last zero-length
+    // procedure at or before it in the target's section contribution. The zero-length
+    // one covers the target if nothing else starts between the two.
+    let before_target = procs.partition_point(|p| p.start <= target);
+    let last_proc_start = procs[..before_target]
+        .iter()
+        .rev()
+        .find(|p| module_matches(&module_names[p.module], module_filter))
+        .map(|p| p.start);
+    let mut zero_length_proc = None;
+
+    // Only the modules with something at the target are read: those with a procedure covering
+    // it, those with a zero-length procedure that may, and the one the target's contribution
+    // belongs to, which has the labels and tables in it. Without a contribution a label from
+    // any module may be the nearest one.
+    let mut module_indices = BTreeSet::new();
+    for i in (0..before_target).rev() {
+        if proc_max_ends[i] <= target {
+            break;
+        }
+        if procs[i].end > target {
+            module_indices.insert(procs[i].module);
+        }
+    }
+    if options.zero_length {
+        for p in procs[..before_target].iter().rev().take_while(|p| Some(p.start) == last_proc_start) {
+            module_indices.insert(p.module);
+        }
+    }
+    match contribution {
+        Some(c) if usize::from(c.module) < module_names.len() => {
+            module_indices.insert(usize::from(c.module));
+        }
+        Some(_) => {}
+        None if target_offset.is_some() => module_indices.extend(0..module_names.len()),
+        None => {}
+    }
+
+    // Labels are only reported when no procedure covers the target, which is the case
+    // for hand-written assembly.
+    let mut found_proc = false;
+    let mut nearest_label: Option<(u32, String)> = None;
+
+    // Range of the last procedure covering the target, used to pick the annotations
+    // that belong to it.
+    let mut proc_range = None;
+
+    // The call site record at the target, or with a return address the last one before it.
+    let mut call_site = None;
+
+    for module_index in module_indices {
+        let module_name = &module_names[module_index];
+        if !module_matches(module_name, module_filter) {
+            continue;
+        }
+
+        let info = match module_info(pdb, dbi, module_infos, module_index)? {
             Some(info) => info,
             None => {
                 //println!("  no module info");
@@ -165,15 +720,22 @@ fn dump_pdb(filename: &str, target: u32) -> pdb::Result<()> {
             }
         };
 
-        let inlinees: BTreeMap<_, _> = info.inlinees()?.map(|i| Ok((i.index(), i))).collect()?;
+        let inlinees: BTreeMap<_, _> = if options.no_inlines {
+            BTreeMap::new()
+        } else {
+            info.inlinees()?.map(|i| Ok((i.index(), i))).collect()?
+        };
 
         let program = info.line_program()?;
+        let mut file_names = FileNameCache::new();
         let mut symbols = info.symbols()?;
 
         let mut depth = 0;
         let mut inc_next = false;
 
         let mut proc_offsets = Vec::new();
+        // Scope depths of the inline sites enclosing the current symbol, innermost last.
+        let mut inline_depths = Vec::new();
 
         while let Some(symbol) = symbols.next()? {
 
@@ -188,57 +750,112 @@ fn dump_pdb(filename: &str, target: u32) -> pdb::Result<()> {
                 if proc_offsets.last().map_or(false, |&(d, _)| d >= depth) {
                     proc_offsets.pop();
                 }
+                if inline_depths.last().is_some_and(|&d| d >= depth) {
+                    inline_depths.pop();
+                }
             }
 
             match symbol.parse() {
                 Ok(SymbolData::Procedure(proc)) => {
                     proc_offsets.push((depth, proc.offset));
-                    
-                    match proc.offset.to_rva(&address_map) {
+
+                    if let Some(start) = proc.offset.to_rva(address_map) {
+                        if start.0 <= target && options.zero_length && proc.len == 0 && precedes_target(proc.offset)
+                            && zero_length_proc.as_ref().is_none_or(|&(s, _)| start.0 >= s)
+                        {
+                            zero_length_proc = Some((start.0, proc.name.to_string().into_owned()));
+                        }
+                    }
+
+                    match proc.offset.to_rva(address_map) {
                         Some(start) if start.0 <= target && target < start.0 + proc.len => {
+                            found_proc = true;
+                            proc_range = Some((start.0, start.0 + proc.len));
                             let sign = if proc.global { "+" } else { "-" };
-                            println!("{} {:?} {:?} {} {:?} {}", sign, symbol.index(), proc.type_index, proc.name, proc.offset.to_rva(&address_map), proc.len);
-
-                            let mut lines = program.lines_at_offset(proc.offset).peekable();
-                            while let Some(line_info) = lines.next()? {
-                                let rva = line_info.offset.to_rva(&address_map).expect("invalid rva");
-                                let length = line_info.length;
-                                let file_info = program.get_file_info(line_info.file_index)?;
-                                let file_name = file_info.name.to_string_lossy(&string_table)?;
-                                match lines.peek()? {
-                                    Some(info) => {
-                                        if rva.0 <= target && info.offset.to_rva(&address_map).expect("invalid rva").0 > target {
-                                            println!("  {} {:?} {}:{}", rva, length, file_name, line_info.line_start);
-                                            break;
-                                        }
+                            writeln!(out, "{} {:?} {:?} {} {:?} {}", sign, symbol.index(), proc.type_index, proc.name, proc.offset.to_rva(address_map), proc.len)?;
+                            writeln!(out, "  offset {:04x}:{:08x}", proc.offset.section, proc.offset.offset)?;
+                            writeln!(out, "  module {} {}", module_index, module_name)?;
+                            if let Some((parent, kind)) = funclet_parent(&proc.name.to_string()) {
+                                // Decorated funclet names embed the decorated parent name.
+                                let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
+                                let demangled = msvc_demangler::demangle(parent, flags).unwrap_or_else(|_| parent.to_string());
+                                writeln!(out, "  funclet {} ({})", demangled, kind)?;
+                                // Funclets are emitted into the same object file as their parent.
+                                let names = [parent, demangled.as_str()];
+                                if let Some(l) = procedure_first_line(info, &program, address_map, string_table, &mut file_names, &names)? {
+                                    writeln!(out, "  funclet parent {}:{}", l.file, l.line)?;
+                                }
+                            }
+
+                            // The records come in one block per file and are only in address order
+                            // within a block, so they're sorted before each one is taken to end at
+                            // the next record or at the end of the procedure.
+                            let mut lines = collect_lines(program.lines_at_offset(proc.offset), &program, address_map, string_table, &mut file_names)?;
+                            infer_line_sizes(&mut lines, u64::from(start.0 + proc.len));
+                            if options.all_lines {
+                                for l in &lines {
+                                    let kind = if l.is_statement { "Statement" } else { "Expression" };
+                                    writeln!(out, "  {:#x} {:x} {}:{}:{}-{} {}", l.address, l.size.unwrap_or(0), l.file, l.line, l.column, l.line_end, kind)?;
+                                }
+                            } else {
+                                let target = u64::from(target);
+                                match lines.iter().find(|l| l.address <= target && target < l.address + l.size.unwrap_or(0)) {
+                                    Some(l) => {
+                                        let kind = if l.is_statement { "Statement" } else { "Expression" };
+                                        writeln!(out, "  {:#x} {:x} {}:{}-{} {}", l.address, l.size.unwrap_or(0), l.file, l.line, l.line_end, kind)?;
                                     }
-                                    _ => println!("  {} {:?} {}:{}", rva, length, file_name, line_info.line_start),
-                                };
+                                    None => writeln!(out, "  no line info")?,
+                                }
                             }
                         }
                         _ => {}
                     }
 
                 }
-                Ok(SymbolData::InlineSite(site)) => {
+                Ok(SymbolData::InlineSite(site)) if !options.no_inlines => {
                     let parent_offset = proc_offsets
                         .last()
                         .map(|&(_, offset)| offset).unwrap();
+                    inline_depths.push(depth);
 
                     // We can assume that inlinees will be listed in the inlinee table. If missing,
                     // skip silently instead of erroring out. Missing a single inline function is
                     // more acceptable in such a case than halting iteration completely.
                     if let Some(inlinee) = inlinees.get(&site.inlinee) {
-                        // println!("Found inline parent_offset {:?} {:?} {:?}", parent_offset.to_rva(&address_map), site, inlinee);
+                        // println!("Found inline parent_offset {:?} {:?} {:?}", parent_offset.to_rva(address_map), site, inlinee);
                         let line_iter = inlinee.lines(parent_offset, &site);
-                        let lines = collect_lines(line_iter, &program, &address_map, &string_table)?;
+                        let lines = collect_lines(line_iter, &program, address_map, string_table, &mut file_names)?;
+
+                        let in_matched_proc = proc_range.is_some()
+                            && parent_offset.to_rva(address_map).map(|rva| rva.0) == proc_range.map(|(start, _)| start);
+                        if options.inline_tree && in_matched_proc {
+                            let indent = "  ".repeat(inline_depths.len());
+                            if names.is_none() {
+                                *names = Some(match id_information(pdb, ipi)? {
+                                    Some(ipi) => inlinee_names(ipi)?,
+                                    None => HashMap::new(),
+                                });
+                            }
+                            let name = names.as_ref().and_then(|names| names.get(&site.inlinee)).map_or("<unknown>", String::as_str);
+                            writeln!(out, "{}inline {} {:?}", indent, name, site.inlinee)?;
+                            for l in &lines {
+                                let end = l.address + l.size.unwrap_or(0);
+                                writeln!(out, "{}  {:x}-{:x} {}:{}", indent, l.address, end, l.file, l.line)?;
+                            }
+                            continue;
+                        }
+
                         for l in lines {
                             if l.address <= target.into() && l.address + l.size.unwrap() > target.into() {
-                                println!("{:?} ({:x?} {:x} {:x?}) {:?}", l, l.address,target, l.address + l.size.unwrap(), site.inlinee);
-                                for i in ipi.iter().iterator() {
-                                    if let Ok(i) = i {
-                                        if i.index() == site.inlinee {
-                                            println!("{:?}", i.parse()?)
+                                // The outer procedure is depth 0, so the innermost inline site has the
+                                // highest depth. Blocks don't count.
+                                writeln!(out, "inline[{}] {:?} ({:x?} {:x} {:x?}) {:?}", inline_depths.len(), l, l.address,target, l.address + l.size.unwrap(), site.inlinee)?;
+                                if let Some(ipi) = id_information(pdb, ipi)? {
+                                    for i in ipi.iter().iterator() {
+                                        if let Ok(i) = i {
+                                            if i.index() == site.inlinee {
+                                                writeln!(out, "{:?}", i.parse()?)?
+                                            }
                                         }
                                     }
                                 }
@@ -246,38 +863,1410 @@ fn dump_pdb(filename: &str, target: u32) -> pdb::Result<()> {
                         }
                     }
                 }
+                Ok(SymbolData::Block(block)) => {
+                    // Nested blocks follow their parents in the stream, so the chain prints
+                    // from the outermost scope inwards.
+                    match block.offset.to_rva(address_map) {
+                        Some(start) if start.0 <= target && target < start.0 + block.len => {
+                            writeln!(out, "  block {} {:x}-{:x}", block.name, start.0, start.0 + block.len)?;
+                        }
+                        _ => {}
+                    }
+                }
+                Err(_) if symbol.raw_kind() == S_ANNOTATION => {
+                    if let Some((offset, strings)) = parse_annotation(symbol.raw_bytes()) {
+                        match (offset.to_rva(address_map), proc_range) {
+                            (Some(rva), Some((start, end))) if start <= rva.0 && rva.0 < end => {
+                                for s in strings {
+                                    writeln!(out, "  annotation {:x} {}", rva.0, s)?;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Err(_) if symbol.raw_kind() == S_CALLSITEINFO => {
+                    if let Some((offset, callee_type)) = parse_call_site_info(symbol.raw_bytes()) {
+                        let at_target = |rva: u32| match options.return_address {
+                            true => rva <= target && target - rva < MAX_INSTRUCTION_LEN,
+                            false => rva == target,
+                        };
+                        match offset.to_rva(address_map) {
+                            Some(rva) if at_target(rva.0) && call_site.is_none_or(|(start, _)| rva.0 >= start) => {
+                                call_site = Some((rva.0, callee_type));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Err(_) if symbol.raw_kind() == S_HEAPALLOCSITE => {
+                    if let Some((offset, len, alloc_type)) = parse_heap_alloc_site(symbol.raw_bytes()) {
+                        match offset.to_rva(address_map) {
+                            Some(rva) if rva.0 <= target && target < rva.0 + u32::from(len) => {
+                                writeln!(out, "  heapalloc {:x} {:?}", rva.0, alloc_type)?;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Err(_) if matches!(symbol.raw_kind(), S_CALLERS | S_CALLEES | S_INLINEES) => {
+                    let parent_rva = proc_offsets.last().and_then(|&(_, offset)| offset.to_rva(address_map));
+                    match (parent_rva, proc_range) {
+                        (Some(rva), Some((start, _))) if rva.0 == start => {}
+                        _ => continue,
+                    }
+                    let kind = match symbol.raw_kind() {
+                        S_CALLERS => "caller",
+                        S_CALLEES => "callee",
+                        _ => "inlinee",
+                    };
+                    for (index, count) in parse_function_list(symbol.raw_bytes()).unwrap_or_default() {
+                        match count {
+                            Some(count) => writeln!(out, "  {} {:#x} {}", kind, index, count)?,
+                            None => writeln!(out, "  {} {:#x}", kind, index)?,
+                        }
+                    }
+                }
+                Err(_) if symbol.raw_kind() == S_ARMSWITCHTABLE => {
+                    // Reported when the address is the indirect branch or the start of its table.
+                    if let Some(table) = parse_switch_table(symbol.raw_bytes()) {
+                        let branch = table.branch.to_rva(address_map).map(|rva| rva.0);
+                        let start = table.table.to_rva(address_map).map(|rva| rva.0);
+                        if branch == Some(target) || start == Some(target) {
+                            writeln!(out, "  switchtable branch {:x?} table {:x?} base {:x?} type {} entries {}",
+                                branch, start, table.base.to_rva(address_map).map(|rva| rva.0), table.switch_type, table.entries)?;
+                        }
+                    }
+                }
+                Ok(SymbolData::Label(label)) => {
+                    match label.offset.to_rva(address_map) {
+                        Some(rva) if precedes_target(label.offset) && nearest_label.as_ref().is_none_or(|&(l, _)| rva.0 >= l) => {
+                            nearest_label = Some((rva.0, label.name.to_string().into_owned()));
+                        }
+                        _ => {}
+                    }
+                }
                 _ => {}
             }
         }
     }
 
+    if let Some((start, callee_type)) = call_site {
+        writeln!(out, "  callsite {:x} {:?}", start, callee_type)?;
+    }
+
+    if let Some((start, name)) = zero_length_proc {
+        if Some(start) == last_proc_start {
+            writeln!(out, "z {} {:x} (zero-length)", name, start)?;
+            found_proc = true;
+        }
+    }
+
+    if !found_proc {
+        // A procedure starting between the label and the target ends the label's code.
+        if let Some((start, name)) = nearest_label {
+            if last_proc_start.is_none_or(|proc_start| proc_start < start) {
+                writeln!(out, "label {} {:x}+{:x}", name, start, target - start)?;
+            }
+        }
+
+        // Global symbols belong to no module, so with a module filter they're only reported when
+        // the target's contribution comes from a matching module.
+        let in_filtered_module = module_filter.is_none()
+            || contribution.and_then(|c| module_names.get(usize::from(c.module))).is_some_and(|name| module_matches(name, module_filter));
+        if !in_filtered_module {
+            return Ok(());
+        }
+
+        let nearest = match target_offset.and_then(|offset| nearest_global(data, offset, precedes_target)) {
+            Some(index) => globals.iter_at(index).next()?,
+            None => None,
+        };
+        if let Some(Ok(SymbolData::Data(data))) = nearest.map(|symbol| symbol.parse()) {
+            if let Some(start) = data.offset.to_rva(address_map) {
+                writeln!(out, "data {}+{:x} {:?}", data.name, target - start.0, data.type_index)?;
+            }
+        }
+
+        // Code without procedure records, like the CFG helpers, only has public symbols.
+        let nearest = match target_offset.and_then(|offset| nearest_global(publics, offset, precedes_target)) {
+            Some(index) => globals.iter_at(index).next()?,
+            None => None,
+        };
+        let nearest = match nearest.map(|symbol| symbol.parse()) {
+            Some(Ok(SymbolData::Public(public))) => public.offset.to_rva(address_map).map(|start| (start.0, public.name.to_string().into_owned())),
+            _ => None,
+        };
+        if let Some((start, name)) = nearest {
+            match guard_helper_label(&name) {
+                Some(label) => writeln!(out, "public {} {:x}+{:x} [synthetic]", label, start, target - start)?,
+                None => {
+                    let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
+                    let name = msvc_demangler::demangle(&name, flags).unwrap_or(name);
+                    writeln!(out, "public {} {:x}+{:x}", name, start, target - start)?;
+                }
+            }
+        }
+    }
+
     Ok(())
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+/// Prints the address and type of every data symbol called `name`.
+fn find_data(filename: &str, name: &str) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
 
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    let matches = match opts.parse(&args[1..]) {
-        Ok(m) => m,
-        Err(f) => panic!(f.to_string()),
-    };
+    let globals = pdb.global_symbols()?;
+    let mut symbols = globals.iter();
+    while let Some(symbol) = symbols.next()? {
+        if let Ok(SymbolData::Data(data)) = symbol.parse() {
+            if data.name.to_string() == name {
+                let sign = if data.global { "+" } else { "-" };
+                println!("{} {} {:?} {:?}", sign, data.name, data.offset.to_rva(&address_map), data.type_index);
+            }
+        }
+    }
 
-    let (filename, address) = if matches.free.len() == 2 {
-        (&matches.free[0], &matches.free[1])
-    } else {
-        //print_usage(&program, opts);
-        println!("specify path to a PDB");
-        return;
+    Ok(())
+}
+
+/// Resolves an offset into a thread's TLS block to the thread-local variable containing it.
+///
+/// The `.tls` section is the template for every TLS block, so the section-relative offset of an
+/// S_GTHREAD32/S_LTHREAD32 record is also its offset within the block.
+fn find_tls<W: Write>(context: &LookupContext<'_>, target: u32, out: &mut W) -> pdb::Result<()> {
+    let after = context.tls.partition_point(|&(offset, _)| offset <= target);
+    let nearest = match after.checked_sub(1) {
+        Some(i) => context.globals.iter_at(context.tls[i].1).next()?,
+        None => None,
     };
-    let address = address.trim_start_matches("0x");
-    let address = u32::from_str_radix(address, 16).unwrap();
 
-    match dump_pdb(&filename, address) {
-        Ok(_) => {}
-        Err(e) => {
-            writeln!(&mut std::io::stderr(), "error dumping PDB: {}", e).expect("stderr write");
+    if let Some(Ok(SymbolData::ThreadStorage(tls))) = nearest.map(|symbol| symbol.parse()) {
+        writeln!(out, "tls {}+{:x} {:?}", tls.name, target - tls.offset.offset, tls.type_index)?;
+    }
+
+    Ok(())
+}
+
+fn print_constants(mut symbols: pdb::SymbolIter<'_>) -> pdb::Result<()> {
+    while let Some(symbol) = symbols.next()? {
+        if let Ok(SymbolData::Constant(constant)) = symbol.parse() {
+            println!("{} {:?} {}", constant.name, constant.type_index, constant.value);
         }
     }
+    Ok(())
+}
+
+/// Lists the S_CONSTANT records from the global and module symbol streams. With a module filter
+/// only the matching modules' streams are listed, since global symbols don't belong to a module.
+fn list_constants(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+
+    if module_filter.is_none() {
+        println!("Global symbols:");
+        let globals = pdb.global_symbols()?;
+        print_constants(globals.iter())?;
+    }
+
+    println!("Module private symbols:");
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        print_constants(info.symbols()?)?;
+    }
+    Ok(())
+}
+
+/// Lists the S_UDT records of every module, so a typedef name can be mapped to its type in
+/// the module that uses it.
+fn list_udts(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::UserDefinedType(udt)) = symbol.parse() {
+                println!("{} {} {:?}", module.module_name(), udt.name, udt.type_index);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints every line record of every procedure in address order.
+fn dump_all_lines(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let string_table = pdb.string_table()?;
+
+    let mut records = Vec::new();
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let program = info.line_program()?;
+        let mut file_names = FileNameCache::new();
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                let start = match proc.offset.to_rva(&address_map) {
+                    Some(rva) => rva.0,
+                    None => continue,
+                };
+                let name = proc.name.to_string().into_owned();
+                let mut lines = collect_lines(program.lines_at_offset(proc.offset), &program, &address_map, &string_table, &mut file_names)?;
+                infer_line_sizes(&mut lines, u64::from(start + proc.len));
+                records.extend(lines.into_iter().map(|l| (name.clone(), l)));
+            }
+        }
+    }
+
+    records.sort_by_key(|(_, l)| l.address);
+    for (name, l) in records {
+        let kind = if l.is_statement { "Statement" } else { "Expression" };
+        println!("{:x} {:x} {} {}:{}-{} {}", l.address, l.size.unwrap_or(0), name, l.file, l.line, l.line_end, kind);
+    }
+    Ok(())
+}
+
+/// Matches `text` against a glob `pattern` where `*` matches any run of characters and `?` any
+/// single character. Case is ignored, since module paths come from Windows.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Classic backtracking over the most recent `*`.
+    let (mut p, mut t) = (0, 0);
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn module_matches(module_name: &str, module_filter: Option<&str>) -> bool {
+    module_filter.is_none_or(|pattern| glob_match(pattern, module_name))
+}
+
+/// A procedure record, owned so that whole-PDB reports can sort and compare them.
+struct ProcedureInfo {
+    rva: u32,
+    offset: pdb::PdbInternalSectionOffset,
+    len: u32,
+    name: String,
+    module: String,
+}
+
+/// Collects every procedure with a valid RVA from all module streams, or only from the modules
+/// whose name matches `module_filter`.
+fn collect_procedures(pdb: &mut PDB<File>, address_map: &AddressMap, module_filter: Option<&str>) -> pdb::Result<Vec<ProcedureInfo>> {
+    let mut procs = Vec::new();
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                if let Some(rva) = proc.offset.to_rva(address_map) {
+                    procs.push(ProcedureInfo {
+                        rva: rva.0,
+                        offset: proc.offset,
+                        len: proc.len,
+                        name: proc.name.to_string().into_owned(),
+                        module: module.module_name().into_owned(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(procs)
+}
+
+/// Prints a linker-map-like report: sections, per-object contributions and procedures by address.
+fn print_map(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+
+    println!(" Start         Length     Rva      Name");
+    for (i, section) in pdb.sections()?.unwrap_or_default().iter().enumerate() {
+        println!(" {:04x}:{:08x} {:08x}H {:08x} {}", i + 1, 0, section.physical_address, section.virtual_address, section.name());
+    }
+
+    let dbi = pdb.debug_information()?;
+    let mut module_names = Vec::new();
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        module_names.push(module.module_name().into_owned());
+    }
+
+    println!();
+    println!(" Start         Length     Object");
+    let mut contributions = dbi.section_contributions()?;
+    while let Some(c) = contributions.next()? {
+        let name = module_names.get(usize::from(c.module)).map_or("<unknown>", |n| n.as_str());
+        if module_filter.is_some_and(|pattern| !glob_match(pattern, name)) {
+            continue;
+        }
+        println!(" {:04x}:{:08x} {:08x}H {}", c.offset.section, c.offset.offset, c.size, name);
+    }
+
+    let mut procs = collect_procedures(&mut pdb, &address_map, module_filter)?;
+    procs.sort_by_key(|p| p.rva);
+
+    println!();
+    println!(" Address       Rva      Length   Procedure");
+    for p in procs {
+        println!(" {:04x}:{:08x} {:08x} {:08x} {}", p.offset.section, p.offset.offset, p.rva, p.len, p.name);
+    }
+    Ok(())
+}
+
+/// Compares the procedures of two PDBs by module and name and prints the ones that were added,
+/// removed or changed size. Overloads and static functions can share a name within a module, so
+/// every size under a name is kept and the sorted lists are compared. Names with more than one
+/// procedure are marked with the count on each side.
+fn diff_pdbs(old_filename: &str, new_filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let mut sizes = Vec::new();
+    for filename in &[old_filename, new_filename] {
+        let file = std::fs::File::open(filename)?;
+        let mut pdb = PDB::open(file)?;
+        let address_map = pdb.address_map()?;
+        let mut by_name: BTreeMap<_, Vec<u32>> = BTreeMap::new();
+        for p in collect_procedures(&mut pdb, &address_map, module_filter)? {
+            by_name.entry((p.module, p.name)).or_default().push(p.len);
+        }
+        for lens in by_name.values_mut() {
+            lens.sort_unstable();
+        }
+        sizes.push(by_name);
+    }
+    let (old, new) = (&sizes[0], &sizes[1]);
+
+    let lengths = |lens: &[u32]| lens.iter().map(|len| format!("{:x}", len)).collect::<Vec<_>>().join(",");
+    let duplicates = |old: &[u32], new: &[u32]| {
+        if old.len() > 1 || new.len() > 1 {
+            format!(" [duplicate name: {} -> {}]", old.len(), new.len())
+        } else {
+            String::new()
+        }
+    };
+    for (key, lens) in old {
+        let (module, name) = key;
+        match new.get(key) {
+            None => println!("- {} {} {}{}", name, lengths(lens), module, duplicates(lens, &[])),
+            Some(new_lens) if new_lens != lens => {
+                println!("~ {} {} -> {} {}{}", name, lengths(lens), lengths(new_lens), module, duplicates(lens, new_lens));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, lens) in new {
+        let (module, name) = key;
+        if !old.contains_key(key) {
+            println!("+ {} {} {}{}", name, lengths(lens), module, duplicates(&[], lens));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the namespace or class a qualified name is declared in, or `<global>`. Template
+/// arguments can contain `::` too, so only separators outside angle brackets count.
+fn name_scope(name: &str) -> &str {
+    let mut depth = 0usize;
+    let mut scope_end = None;
+    for (i, c) in name.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 && name[i + 1..].starts_with(':') => scope_end = Some(i),
+            _ => {}
+        }
+    }
+    match scope_end {
+        Some(end) if end > 0 => &name[..end],
+        _ => "<global>",
+    }
+}
+
+/// Prints the function size distribution, the `count` largest procedures and the total
+/// procedure size per module and per namespace.
+fn print_size_stats(filename: &str, count: usize, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let mut procs = collect_procedures(&mut pdb, &address_map, module_filter)?;
+
+    let total: u64 = procs.iter().map(|p| u64::from(p.len)).sum();
+    println!("{} procedures, {:x} bytes", procs.len(), total);
+
+    // Buckets by power of two: bucket n holds sizes in [2^(n-1), 2^n).
+    let mut buckets = BTreeMap::new();
+    for p in &procs {
+        *buckets.entry(32 - p.len.leading_zeros()).or_insert(0) += 1;
+    }
+    println!();
+    for (bucket, n) in buckets {
+        println!("< {:x}: {}", 1u64 << bucket, n);
+    }
+
+    let mut per_module = BTreeMap::new();
+    for p in &procs {
+        *per_module.entry(p.module.as_str()).or_insert(0u64) += u64::from(p.len);
+    }
+    let mut per_module: Vec<_> = per_module.into_iter().collect();
+    per_module.sort_by_key(|&(_, size)| Reverse(size));
+    println!();
+    for (module, size) in per_module {
+        println!("{:x} {}", size, module);
+    }
+
+    // Decorated names are demangled first so that they group with the undecorated ones.
+    let mut per_scope = BTreeMap::new();
+    for p in &procs {
+        let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
+        let name = if p.name.starts_with('?') {
+            msvc_demangler::demangle(&p.name, flags).unwrap_or_else(|_| p.name.clone())
+        } else {
+            p.name.clone()
+        };
+        *per_scope.entry(name_scope(&name).to_string()).or_insert(0u64) += u64::from(p.len);
+    }
+    let mut per_scope: Vec<_> = per_scope.into_iter().collect();
+    per_scope.sort_by_key(|&(_, size)| Reverse(size));
+    println!();
+    for (scope, size) in per_scope {
+        println!("{:x} {}", size, scope);
+    }
+
+    procs.sort_by_key(|p| Reverse(p.len));
+    println!();
+    for p in procs.iter().take(count) {
+        println!("{:x} {:x} {}", p.len, p.rva, p.name);
+    }
+    Ok(())
+}
+
+/// `IMAGE_SCN_CNT_CODE`, set on section contributions that contain code.
+const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+
+/// Prints, per module, how many bytes of its code have line info, how many are only covered by a
+/// procedure symbol, and how many have neither.
+fn print_coverage(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let dbi = pdb.debug_information()?;
+
+    let mut code_sizes = BTreeMap::new();
+    let mut contributions = dbi.section_contributions()?;
+    while let Some(c) = contributions.next()? {
+        if c.characteristics & IMAGE_SCN_CNT_CODE != 0 {
+            *code_sizes.entry(usize::from(c.module)).or_insert(0u64) += u64::from(c.size);
+        }
+    }
+
+    println!("code     lines    proc     none     module");
+    let mut modules = dbi.modules()?;
+    let mut module_index = 0;
+    while let Some(module) = modules.next()? {
+        let code = code_sizes.get(&module_index).copied().unwrap_or(0);
+        module_index += 1;
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => {
+                println!("{:08x} {:08x} {:08x} {:08x} {}", code, 0, 0, code, module.module_name());
+                continue;
+            }
+        };
+
+        let program = info.line_program()?;
+        let mut proc_bytes = 0u64;
+        let mut line_bytes = 0u64;
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                proc_bytes += u64::from(proc.len);
+
+                // Module line records have no length: each one runs up to the next record of the
+                // procedure and the last one up to its end, so everything from the first record on
+                // is covered.
+                let proc_end = proc.offset.offset + proc.len;
+                let mut first_line = None;
+                let mut lines = program.lines_at_offset(proc.offset);
+                while let Some(line) = lines.next()? {
+                    let offset = line.offset.offset;
+                    if first_line.is_none_or(|first| offset < first) {
+                        first_line = Some(offset);
+                    }
+                }
+                if let Some(first_line) = first_line {
+                    line_bytes += u64::from(proc_end.saturating_sub(first_line));
+                }
+            }
+        }
+
+        let proc_only = proc_bytes.saturating_sub(line_bytes);
+        let none = code.saturating_sub(proc_bytes.max(line_bytes));
+        println!("{:08x} {:08x} {:08x} {:08x} {}", code, line_bytes, proc_only, none, module.module_name());
+    }
+    Ok(())
+}
+
+/// Prints pairs of procedures whose ranges overlap without starting at the same address.
+///
+/// A lookup prints every procedure covering the address, so both members of a pair show up.
+fn print_overlaps(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let mut procs = collect_procedures(&mut pdb, &address_map, module_filter)?;
+    procs.sort_by_key(|p| (p.rva, p.len));
+
+    // The procedures whose ranges are still open at the current start, which are the ones it
+    // overlaps with.
+    let mut active: Vec<&ProcedureInfo> = Vec::new();
+    for p in &procs {
+        active.retain(|a| a.rva + a.len > p.rva);
+        for a in &active {
+            if a.rva < p.rva {
+                println!("{:x}-{:x} {} overlaps {:x}-{:x} {}", a.rva, a.rva + a.len, a.name, p.rva, p.rva + p.len, p.name);
+            }
+        }
+        active.push(p);
+    }
+    Ok(())
+}
+
+/// Checks the PDB against the debug id from an image's CodeView record, given as the GUID in
+/// hex followed by the age in hex, as used by symbol servers.
+fn verify_pdb(filename: &str, debug_id: &str) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let info = pdb.pdb_information()?;
+    let dbi = pdb.debug_information()?;
+
+    let actual_guid = format!("{:X}", info.guid.to_simple());
+    let actual_age = dbi.age().unwrap_or(info.age);
+    let actual = format!("{}{:x}", actual_guid, actual_age);
+
+    let expected = debug_id.to_uppercase();
+    let matches = match (expected.get(..32), expected.get(32..).map(|age| u32::from_str_radix(age, 16))) {
+        (Some(guid), Some(Ok(age))) => guid == actual_guid && age == actual_age,
+        _ => {
+            println!("invalid debug id {}", debug_id);
+            return Ok(());
+        }
+    };
+
+    if matches {
+        println!("match {}", actual);
+    } else {
+        println!("mismatch: expected {}, PDB has {}", debug_id, actual);
+    }
+    Ok(())
+}
+
+/// Calls `f` with the name of the outer procedure, the inlinee and the line records of every inline
+/// site in the modules matching `module_filter`.
+fn for_each_inline_site<F>(
+    pdb: &mut PDB<File>,
+    address_map: &AddressMap,
+    string_table: &pdb::StringTable,
+    module_filter: Option<&str>,
+    mut f: F,
+) -> pdb::Result<()>
+where
+    F: FnMut(&str, pdb::IdIndex, Vec<LineInfo>),
+{
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let inlinees: BTreeMap<_, _> = info.inlinees()?.map(|i| Ok((i.index(), i))).collect()?;
+        let program = info.line_program()?;
+        let mut file_names = FileNameCache::new();
+        let mut symbols = info.symbols()?;
+
+        let mut depth = 0;
+        let mut inc_next = false;
+        let mut procs: Vec<(i32, pdb::PdbInternalSectionOffset, String)> = Vec::new();
+
+        while let Some(symbol) = symbols.next()? {
+            if inc_next {
+                depth += 1;
+            }
+            inc_next = symbol.starts_scope();
+            if symbol.ends_scope() {
+                depth -= 1;
+                if procs.last().is_some_and(|&(d, _, _)| d >= depth) {
+                    procs.pop();
+                }
+            }
+
+            match symbol.parse() {
+                Ok(SymbolData::Procedure(proc)) => {
+                    procs.push((depth, proc.offset, proc.name.to_string().into_owned()));
+                }
+                Ok(SymbolData::InlineSite(site)) => {
+                    if let (Some((_, parent_offset, parent_name)), Some(inlinee)) = (procs.last(), inlinees.get(&site.inlinee)) {
+                        let line_iter = inlinee.lines(*parent_offset, &site);
+                        let lines = collect_lines(line_iter, &program, address_map, string_table, &mut file_names)?;
+                        f(parent_name, site.inlinee, lines);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints every range where the given function was inlined, with the procedure it was inlined
+/// into. `function` is either an IPI index in hex with a `0x` prefix or a function name.
+fn find_inline_sites(filename: &str, function: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let string_table = pdb.string_table()?;
+
+    let mut wanted = Vec::new();
+    if let Some(index) = function.strip_prefix("0x") {
+        match u32::from_str_radix(index, 16) {
+            Ok(index) => wanted.push(pdb::IdIndex(index)),
+            Err(e) => {
+                writeln!(&mut std::io::stderr(), "invalid id {}: {}", function, e).expect("stderr write");
+                return Ok(());
+            }
+        }
+    } else {
+        let ipi = pdb.id_information()?;
+        let mut items = ipi.iter();
+        while let Some(item) = items.next()? {
+            let name = match item.parse() {
+                Ok(pdb::IdData::Function(f)) => f.name,
+                Ok(pdb::IdData::MemberFunction(f)) => f.name,
+                _ => continue,
+            };
+            if name.to_string() == function {
+                wanted.push(item.index());
+            }
+        }
+    }
+
+    for_each_inline_site(&mut pdb, &address_map, &string_table, module_filter, |parent, inlinee, lines| {
+        if wanted.contains(&inlinee) {
+            for l in lines {
+                println!("{:x}-{:x} {:?} in {} {}:{}", l.address, l.address + l.size.unwrap_or(0), inlinee, parent, l.file, l.line);
+            }
+        }
+    })
+}
+
+/// Prints the `count` inlined functions responsible for the most code bytes across all their
+/// inline sites, with the number of sites.
+fn print_inline_bloat(filename: &str, count: usize, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let string_table = pdb.string_table()?;
+
+    let mut totals: BTreeMap<pdb::IdIndex, (u64, u32)> = BTreeMap::new();
+    for_each_inline_site(&mut pdb, &address_map, &string_table, module_filter, |_, inlinee, lines| {
+        let total = totals.entry(inlinee).or_default();
+        total.0 += lines.iter().map(|l| l.size.unwrap_or(0)).sum::<u64>();
+        total.1 += 1;
+    })?;
+
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by_key(|&(_, (bytes, _))| Reverse(bytes));
+
+    let mut ipi = None;
+    let names = match id_information(&mut pdb, &mut ipi)? {
+        Some(ipi) => inlinee_names(ipi)?,
+        None => HashMap::new(),
+    };
+    for (inlinee, (bytes, sites)) in totals.into_iter().take(count) {
+        let name = names.get(&inlinee).map_or("<unknown>", String::as_str);
+        println!("{:x} {} {:?} {}", bytes, sites, inlinee, name);
+    }
+    Ok(())
+}
+
+/// Prints every address that more than one procedure starts at, which is what identical code
+/// folding leaves behind, with all the names folded together there.
+fn print_icf_groups(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+
+    let mut groups: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for p in collect_procedures(&mut pdb, &address_map, module_filter)? {
+        groups.entry(p.rva).or_default().push(p.name);
+    }
+
+    for (rva, mut names) in groups {
+        names.sort();
+        names.dedup();
+        if names.len() > 1 {
+            println!("{:x}", rva);
+            for name in names {
+                println!("  {}", name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints every source file referenced by a line program, with its checksum kind and the
+/// modules referencing it.
+fn list_source_files(filename: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let string_table = pdb.string_table()?;
+
+    let mut files: BTreeMap<String, (&'static str, Vec<String>)> = BTreeMap::new();
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let program = info.line_program()?;
+        let mut program_files = program.files();
+        while let Some(file_info) = program_files.next()? {
+            let name = file_info.name.to_string_lossy(&string_table)?.into_owned();
+            let checksum = match file_info.checksum {
+                pdb::FileChecksum::None => "none",
+                pdb::FileChecksum::Md5(_) => "md5",
+                pdb::FileChecksum::Sha1(_) => "sha1",
+                pdb::FileChecksum::Sha256(_) => "sha256",
+            };
+            let entry = files.entry(name).or_insert((checksum, Vec::new()));
+            entry.1.push(module.module_name().into_owned());
+        }
+    }
+
+    for (name, (checksum, modules)) in files {
+        println!("{} {}", name, checksum);
+        for module in modules {
+            println!("  {}", module);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the procedures whose line records reference `source_file`, compared case-insensitively.
+fn list_functions_in_file(filename: &str, source_file: &str, module_filter: Option<&str>) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+    let string_table = pdb.string_table()?;
+    let source_file = source_file.to_lowercase();
+
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let program = info.line_program()?;
+        let mut file_names = FileNameCache::new();
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                let lines = collect_lines(program.lines_at_offset(proc.offset), &program, &address_map, &string_table, &mut file_names)?;
+                if !lines.iter().any(|l| l.file.to_lowercase() == source_file) {
+                    continue;
+                }
+                if let Some(start) = proc.offset.to_rva(&address_map) {
+                    println!("{:x}-{:x} {}", start.0, start.0 + proc.len, proc.name);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes procedures, public symbols and global data sorted by address in the style of `nm -C`:
+/// the RVA, a type letter (upper case for global symbols) and the name. With a module filter only
+/// the matching modules' procedures are written, since publics and global data don't belong to a
+/// module.
+fn write_symbols<W: Write>(filename: &str, module_filter: Option<&str>, out: &mut W) -> pdb::Result<()> {
+    let file = std::fs::File::open(filename)?;
+    let mut pdb = PDB::open(file)?;
+    let address_map = pdb.address_map()?;
+
+    let mut symbols_by_rva = Vec::new();
+    let mut push = |offset: pdb::PdbInternalSectionOffset, letter: char, name: String| {
+        if let Some(rva) = offset.to_rva(&address_map) {
+            symbols_by_rva.push((rva.0, letter, name));
+        }
+    };
+
+    if module_filter.is_none() {
+        let globals = pdb.global_symbols()?;
+        let mut symbols = globals.iter();
+        while let Some(symbol) = symbols.next()? {
+            match symbol.parse() {
+                Ok(SymbolData::Public(public)) => {
+                    let raw = public.name.to_string();
+                    let flags = msvc_demangler::DemangleFlags::NAME_ONLY;
+                    let name = msvc_demangler::demangle(&raw, flags).unwrap_or_else(|_| raw.into_owned());
+                    push(public.offset, if public.function || public.code { 'T' } else { 'D' }, name);
+                }
+                Ok(SymbolData::Data(data)) => {
+                    push(data.offset, if data.global { 'D' } else { 'd' }, data.name.to_string().into_owned());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let dbi = pdb.debug_information()?;
+    let mut modules = dbi.modules()?;
+    while let Some(module) = modules.next()? {
+        if !module_matches(&module.module_name(), module_filter) {
+            continue;
+        }
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let mut symbols = info.symbols()?;
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Procedure(proc)) = symbol.parse() {
+                push(proc.offset, if proc.global { 'T' } else { 't' }, proc.name.to_string().into_owned());
+            }
+        }
+    }
+
+    symbols_by_rva.sort();
+    for (rva, letter, name) in symbols_by_rva {
+        writeln!(out, "{:08x} {} {}", rva, letter, name)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("z", "zero-length", "treat zero-length procedures as extending to the next procedure");
+    opts.optflag("l", "lines", "print the whole line table of the matching procedure");
+    opts.optopt("m", "module", "only look at modules whose path matches a glob", "GLOB");
+    opts.optopt("", "extra", "also report ranges listed in a tab-separated file", "FILE");
+    opts.optflag("", "no-inlines", "only report the outer procedure, skipping inline sites");
+    opts.optflag("", "inline-tree", "print all inline sites of the matching procedure");
+    opts.optflag("", "dump-lines", "print every line record in the PDB in address order instead");
+    opts.optflag("", "map", "print a linker-map-like report instead");
+    opts.optopt("", "diff", "compare the procedures against another PDB instead", "OLD_PDB");
+    opts.optopt("", "largest", "print size statistics and the N largest procedures instead", "N");
+    opts.optflag("", "coverage", "print per-module line and symbol coverage instead");
+    opts.optflag("", "overlaps", "print procedures with overlapping ranges instead");
+    opts.optopt("", "verify", "check the PDB against an image's debug id instead", "DEBUG_ID");
+    opts.optopt("", "inlined-at", "print every range where a function was inlined instead", "NAME|0xID");
+    opts.optopt("", "inline-bloat", "print the N inlined functions with the most code instead", "N");
+    opts.optflag("", "icf", "print groups of procedures folded to the same address instead");
+    opts.optflag("", "files", "list the source files referenced by the PDB instead");
+    opts.optopt("", "functions-in", "list the procedures with code from a source file instead", "PATH");
+    opts.optflag("", "nm", "list all symbols like nm -C instead");
+    opts.optflag("", "constants", "list the named constants in the PDB instead");
+    opts.optflag("", "udts", "list each module's typedefs (S_UDT) instead");
+    opts.optflag("r", "return-address", "the address is a return address; look up the call before it");
+    opts.optflag("t", "tls", "treat the address as an offset into a TLS block");
+    opts.optopt("d", "data", "print the address and type of a global variable instead", "NAME");
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    let filename = match matches.free.first() {
+        Some(filename) => filename,
+        None => {
+            //print_usage(&program, opts);
+            println!("specify path to a PDB");
+            return;
+        }
+    };
+
+    let module_filter = matches.opt_str("module");
+    let module_filter = module_filter.as_deref();
+
+    // Global variables and TLS slots only have global symbols, which belong to no module.
+    if module_filter.is_some() && (matches.opt_present("d") || matches.opt_present("t")) {
+        writeln!(&mut std::io::stderr(), "--module can't be combined with -d or -t").expect("stderr write");
+        return;
+    }
+    // A TLS offset isn't a code address, so there is no call to step back into.
+    if matches.opt_present("r") && matches.opt_present("t") {
+        writeln!(&mut std::io::stderr(), "-r can't be combined with -t").expect("stderr write");
+        return;
+    }
+
+    if let Some(name) = matches.opt_str("d") {
+        report(find_data(filename, &name));
+        return;
+    }
+    if matches.opt_present("dump-lines") {
+        report(dump_all_lines(filename, module_filter));
+        return;
+    }
+    if let Some(debug_id) = matches.opt_str("verify") {
+        report(verify_pdb(filename, &debug_id));
+        return;
+    }
+    if let Some(function) = matches.opt_str("inlined-at") {
+        report(find_inline_sites(filename, &function, module_filter));
+        return;
+    }
+    if let Some(count) = matches.opt_str("inline-bloat") {
+        match count.parse() {
+            Ok(count) => report(print_inline_bloat(filename, count, module_filter)),
+            Err(e) => writeln!(&mut std::io::stderr(), "invalid count {}: {}", count, e).expect("stderr write"),
+        }
+        return;
+    }
+    if let Some(old_filename) = matches.opt_str("diff") {
+        report(diff_pdbs(&old_filename, filename, module_filter));
+        return;
+    }
+    if let Some(count) = matches.opt_str("largest") {
+        match count.parse() {
+            Ok(count) => report(print_size_stats(filename, count, module_filter)),
+            Err(e) => writeln!(&mut std::io::stderr(), "invalid count {}: {}", count, e).expect("stderr write"),
+        }
+        return;
+    }
+    if matches.opt_present("coverage") {
+        report(print_coverage(filename, module_filter));
+        return;
+    }
+    if let Some(source_file) = matches.opt_str("functions-in") {
+        report(list_functions_in_file(filename, &source_file, module_filter));
+        return;
+    }
+    if matches.opt_present("nm") {
+        let stdout = std::io::stdout();
+        report(write_symbols(filename, module_filter, &mut stdout.lock()));
+        return;
+    }
+    if matches.opt_present("files") {
+        report(list_source_files(filename, module_filter));
+        return;
+    }
+    if matches.opt_present("icf") {
+        report(print_icf_groups(filename, module_filter));
+        return;
+    }
+    if matches.opt_present("overlaps") {
+        report(print_overlaps(filename, module_filter));
+        return;
+    }
+    if matches.opt_present("map") {
+        report(print_map(filename, module_filter));
+        return;
+    }
+    if matches.opt_present("constants") {
+        report(list_constants(filename, module_filter));
+        return;
+    }
+    if matches.opt_present("udts") {
+        report(list_udts(filename, module_filter));
+        return;
+    }
+
+    let addresses = &matches.free[1..];
+
+    let options = DumpOptions {
+        zero_length: matches.opt_present("z"),
+        all_lines: matches.opt_present("l"),
+        inline_tree: matches.opt_present("inline-tree"),
+        no_inlines: matches.opt_present("no-inlines"),
+        module_filter: matches.opt_str("module"),
+        extra_ranges: match matches.opt_str("extra") {
+            Some(path) => match read_extra_ranges(&path) {
+                Ok(ranges) => ranges,
+                Err(e) => {
+                    writeln!(&mut std::io::stderr(), "error reading {}: {}", path, e).expect("stderr write");
+                    return;
+                }
+            },
+            None => Vec::new(),
+        },
+        return_address: matches.opt_present("r"),
+    };
+
+    let mut context = match open_lookup_context(filename) {
+        Ok(context) => context,
+        Err(e) => {
+            report(Err(e));
+            return;
+        }
+    };
+
+    let parse_address = |input: &str| u32::from_str_radix(input.trim_start_matches("0x"), 16);
+
+    // Looks up one address, writing what's found to `out`.
+    let mut lookup = |mut address: u32, mut out: &mut dyn Write| -> pdb::Result<()> {
+        // A return address points just past the call, which may already be the next line or even
+        // the next function, so look up the last byte of the call instruction instead.
+        if matches.opt_present("r") && address > 0 {
+            writeln!(out, "return address {:x}, looking up {:x}", address, address - 1)?;
+            address -= 1;
+        }
+        if matches.opt_present("t") {
+            find_tls(&context, address, &mut out)
+        } else {
+            dump_pdb(&mut context, address, &options, &mut out)
+        }
+    };
+
+    // With no addresses on the command line, read one per line from stdin and answer each as it
+    // arrives, so a trace can be piped through without being collected first. Input that isn't a
+    // hex address is reported and skipped.
+    if addresses.is_empty() {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    writeln!(&mut std::io::stderr(), "error reading stdin: {}", e).expect("stderr write");
+                    return;
+                }
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match parse_address(line) {
+                Ok(address) => {
+                    println!("{:x}:", address);
+                    report(lookup(address, &mut std::io::stdout()));
+                }
+                Err(e) => writeln!(&mut std::io::stderr(), "invalid address {}: {}", line, e).expect("stderr write"),
+            }
+        }
+        return;
+    }
+
+    // Each distinct address is looked up once, in address order so neighbouring lookups share the
+    // modules they read, and the results are then printed in the order given, with a header
+    // naming the address when there are several.
+    let mut unique: Vec<u32> = addresses.iter().filter_map(|input| parse_address(input).ok()).collect();
+    unique.sort_unstable();
+    unique.dedup();
+    let mut results = HashMap::new();
+    for address in unique {
+        let mut out = Vec::new();
+        let result = lookup(address, &mut out).map_err(|e| e.to_string());
+        results.insert(address, (out, result));
+    }
+
+    for input in addresses {
+        let address = match parse_address(input) {
+            Ok(address) => address,
+            Err(e) => {
+                writeln!(&mut std::io::stderr(), "invalid address {}: {}", input, e).expect("stderr write");
+                continue;
+            }
+        };
+        if addresses.len() > 1 {
+            println!("{:x}:", address);
+        }
+        let (out, result) = &results[&address];
+        std::io::stdout().write_all(out).expect("stdout write");
+        if let Err(e) = result {
+            report_error(e);
+        }
+    }
+}
+
+fn report(result: pdb::Result<()>) {
+    match result {
+        Ok(_) => {}
+        Err(e) => report_error(&e),
+    }
+}
+
+fn report_error(e: &dyn std::fmt::Display) {
+    writeln!(&mut std::io::stderr(), "error dumping PDB: {}", e).expect("stderr write");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("foo*", "foo.obj"));
+        assert!(glob_match("*foo", "barfoo"));
+        assert!(glob_match("?.obj", "a.obj"));
+        assert!(!glob_match("?.obj", ".obj"));
+        assert!(!glob_match("a*b", "acbd"));
+    }
+
+    #[test]
+    fn glob_match_backtracks_over_star() {
+        assert!(glob_match("*ab", "aab"));
+        assert!(glob_match("*a*b", "xaybzb"));
+        assert!(glob_match("a*b*c", "abxbc"));
+        assert!(!glob_match("*a*b", "xaybz"));
+    }
+
+    #[test]
+    fn glob_match_trailing_stars_and_case() {
+        assert!(glob_match("abc**", "abc"));
+        assert!(!glob_match("abc*?", "abc"));
+        assert!(glob_match("*\\Foo.OBJ", "c:\\src\\foo.obj"));
+    }
+
+    #[test]
+    fn funclet_parent_x86() {
+        assert_eq!(funclet_parent("__ehhandler$?Foo@@YAXXZ"), Some(("?Foo@@YAXXZ", "EH handler")));
+        assert_eq!(funclet_parent("__unwindfunclet$?Foo@@YAXXZ$0"), Some(("?Foo@@YAXXZ", "unwind funclet")));
+        assert_eq!(funclet_parent("__catch$?Foo@@YAXXZ$2"), Some(("?Foo@@YAXXZ", "catch handler")));
+        assert_eq!(funclet_parent("__catch$?Foo$bar@@YAXXZ$0"), Some(("?Foo$bar@@YAXXZ", "catch handler")));
+    }
+
+    #[test]
+    fn funclet_parent_x64() {
+        assert_eq!(funclet_parent("`Foo'::`1'::catch$2"), Some(("Foo", "catch handler")));
+        assert_eq!(funclet_parent("`Foo'::`1'::dtor$5"), Some(("Foo", "unwind funclet")));
+        assert_eq!(funclet_parent("`Foo'::`1'::fin$0"), Some(("Foo", "finally handler")));
+        assert_eq!(funclet_parent("`Foo'::`1'::filt$0"), Some(("Foo", "exception filter")));
+        assert_eq!(funclet_parent("`ns::Foo'::`2'::catch$3"), Some(("ns::Foo", "catch handler")));
+    }
+
+    #[test]
+    fn funclet_parent_x64_decorated() {
+        assert_eq!(funclet_parent("?catch$2@?0??Foo@@YAXXZ@4HA"), Some(("?Foo@@YAXXZ", "catch handler")));
+        assert_eq!(funclet_parent("?dtor$5@?0??Foo@@YAXXZ@4HA"), Some(("?Foo@@YAXXZ", "unwind funclet")));
+        assert_eq!(funclet_parent("?fin$0@?0??Bar@ns@@QEAAXH@Z@4HA"), Some(("?Bar@ns@@QEAAXH@Z", "finally handler")));
+        assert_eq!(funclet_parent("?filt$0@?1??Foo@@YAXXZ@4HA"), Some(("?Foo@@YAXXZ", "exception filter")));
+    }
+
+    #[test]
+    fn funclet_parent_rejects_other_names() {
+        assert_eq!(funclet_parent("Foo"), None);
+        assert_eq!(funclet_parent("`string'"), None);
+        assert_eq!(funclet_parent("`Foo'::`1'::bar$0"), None);
+        assert_eq!(funclet_parent("?Foo@@YAXXZ"), None);
+        assert_eq!(funclet_parent("?bar$0@?0??Foo@@YAXXZ@4HA"), None);
+    }
+
+    #[test]
+    fn guard_helper_labels() {
+        assert_eq!(guard_helper_label("__guard_check_icall"), Some("guard_check_icall"));
+        assert_eq!(guard_helper_label("_guard_check_icall_nop"), Some("guard_check_icall"));
+        assert_eq!(guard_helper_label("__guard_dispatch_icall_nop"), Some("guard_dispatch_icall"));
+        assert_eq!(guard_helper_label("guard_xfg_dispatch_icall"), Some("guard_xfg_dispatch_icall"));
+        assert_eq!(guard_helper_label("__guard_check_icall_fptr"), None);
+        assert_eq!(guard_helper_label("memcpy"), None);
+    }
+
+    /// Builds a raw symbol record, starting at its kind, from little-endian fields.
+    fn record(kind: u16, fields: &[&[u8]]) -> Vec<u8> {
+        let mut data = kind.to_le_bytes().to_vec();
+        for field in fields {
+            data.extend_from_slice(field);
+        }
+        data
+    }
+
+    fn section_offset(section: u16, offset: u32) -> pdb::PdbInternalSectionOffset {
+        pdb::PdbInternalSectionOffset { section, offset }
+    }
+
+    #[test]
+    fn raw_offset_reads_offset_then_section() {
+        let data = record(S_ANNOTATION, &[&0x1234u32.to_le_bytes(), &2u16.to_le_bytes()]);
+        assert_eq!(raw_offset(&data), Some(section_offset(2, 0x1234)));
+        assert_eq!(raw_offset(&data[..7]), None);
+    }
+
+    #[test]
+    fn parse_annotation_strings() {
+        let data = record(S_ANNOTATION, &[&0x10u32.to_le_bytes(), &1u16.to_le_bytes(), &2u16.to_le_bytes(), b"foo\0bar\0\0"]);
+        let (offset, strings) = parse_annotation(&data).unwrap();
+        assert_eq!(offset, section_offset(1, 0x10));
+        assert_eq!(strings, vec!["foo".to_string(), "bar".to_string()]);
+
+        let data = record(S_ANNOTATION, &[&0x10u32.to_le_bytes(), &1u16.to_le_bytes(), &0u16.to_le_bytes()]);
+        assert_eq!(parse_annotation(&data).unwrap().1, Vec::<String>::new());
+    }
+
+    #[test]
+    fn parse_annotation_truncated() {
+        let data = record(S_ANNOTATION, &[&0x10u32.to_le_bytes(), &1u16.to_le_bytes(), &2u16.to_le_bytes()]);
+        assert!(parse_annotation(&data[..9]).is_none());
+        assert!(parse_annotation(&data[..4]).is_none());
+    }
+
+    #[test]
+    fn parse_call_site_info_fields() {
+        let data = record(S_CALLSITEINFO, &[&0x20u32.to_le_bytes(), &1u16.to_le_bytes(), &0u16.to_le_bytes(), &0x1003u32.to_le_bytes()]);
+        assert_eq!(parse_call_site_info(&data), Some((section_offset(1, 0x20), pdb::TypeIndex(0x1003))));
+        assert_eq!(parse_call_site_info(&data[..13]), None);
+    }
+
+    #[test]
+    fn parse_heap_alloc_site_fields() {
+        let data = record(S_HEAPALLOCSITE, &[&0x30u32.to_le_bytes(), &1u16.to_le_bytes(), &5u16.to_le_bytes(), &0x1010u32.to_le_bytes()]);
+        assert_eq!(parse_heap_alloc_site(&data), Some((section_offset(1, 0x30), 5, pdb::TypeIndex(0x1010))));
+        assert_eq!(parse_heap_alloc_site(&data[..13]), None);
+        assert_eq!(parse_heap_alloc_site(&data[..9]), None);
+    }
+
+    #[test]
+    fn parse_function_list_with_counts() {
+        let data = record(S_CALLEES, &[&2u32.to_le_bytes(), &0x1001u32.to_le_bytes(), &0x1002u32.to_le_bytes(), &7u32.to_le_bytes(), &9u32.to_le_bytes()]);
+        assert_eq!(parse_function_list(&data), Some(vec![(0x1001, Some(7)), (0x1002, Some(9))]));
+    }
+
+    #[test]
+    fn parse_function_list_without_counts() {
+        let data = record(S_INLINEES, &[&2u32.to_le_bytes(), &0x1001u32.to_le_bytes(), &0x1002u32.to_le_bytes()]);
+        assert_eq!(parse_function_list(&data), Some(vec![(0x1001, None), (0x1002, None)]));
+        assert_eq!(parse_function_list(&data[..13]), None);
+        assert_eq!(parse_function_list(&data[..5]), None);
+    }
+
+    #[test]
+    fn parse_switch_table_fields() {
+        let data = record(S_ARMSWITCHTABLE, &[
+            &0x40u32.to_le_bytes(), &2u16.to_le_bytes(), // base
+            &4u16.to_le_bytes(), // type
+            &0x100u32.to_le_bytes(), &0x200u32.to_le_bytes(), // branch and table offsets
+            &1u16.to_le_bytes(), &2u16.to_le_bytes(), // branch and table sections
+            &12u32.to_le_bytes(),
+        ]);
+        let table = parse_switch_table(&data).unwrap();
+        assert_eq!(table.base, section_offset(2, 0x40));
+        assert_eq!(table.switch_type, 4);
+        assert_eq!(table.branch, section_offset(1, 0x100));
+        assert_eq!(table.table, section_offset(2, 0x200));
+        assert_eq!(table.entries, 12);
+        assert!(parse_switch_table(&data[..25]).is_none());
+    }
+
+    fn line(address: u64, size: Option<u64>, line: u64) -> LineInfo {
+        LineInfo { address, size, file: "a.cpp".to_string(), line, column: 0, line_end: line, is_statement: true }
+    }
+
+    #[test]
+    fn infer_line_sizes_sorts_and_fills_gaps() {
+        // Two files' blocks, each sorted only within itself.
+        let mut lines = vec![line(0x10, None, 1), line(0x30, None, 2), line(0x20, None, 10), line(0x28, Some(4), 11)];
+        infer_line_sizes(&mut lines, 0x40);
+        let ranges: Vec<_> = lines.iter().map(|l| (l.address, l.size, l.line)).collect();
+        assert_eq!(ranges, vec![(0x10, Some(0x10), 1), (0x20, Some(8), 10), (0x28, Some(4), 11), (0x30, Some(0x10), 2)]);
+    }
+
+    #[test]
+    fn infer_line_sizes_clamps_to_end() {
+        let mut lines = vec![line(0x10, None, 1), line(0x50, None, 2)];
+        infer_line_sizes(&mut lines, 0x40);
+        assert_eq!(lines[0].size, Some(0x30));
+        assert_eq!(lines[1].size, Some(0));
+
+        let mut lines = Vec::new();
+        infer_line_sizes(&mut lines, 0x40);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn parse_extra_ranges_fields() {
+        let (ranges, bad_lines) = parse_extra_ranges("1000\t1100\tjit_a\n0x2000\t2080\tjit_b\tfoo.js:12\n\n");
+        assert!(bad_lines.is_empty());
+        assert_eq!(ranges.len(), 2);
+        assert_eq!((ranges[0].start, ranges[0].end, ranges[0].name.as_str()), (0x1000, 0x1100, "jit_a"));
+        assert_eq!(ranges[0].location, None);
+        assert_eq!((ranges[1].start, ranges[1].end, ranges[1].name.as_str()), (0x2000, 0x2080, "jit_b"));
+        assert_eq!(ranges[1].location.as_deref(), Some("foo.js:12"));
+    }
+
+    #[test]
+    fn parse_extra_ranges_reports_bad_lines() {
+        let (ranges, bad_lines) = parse_extra_ranges("1000\t1100\tjit_a\nzz\t1100\tjit_b\n\n2000\t2080\n3000 3080 jit_c\n");
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(bad_lines, vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn name_scope_of_qualified_names() {
+        assert_eq!(name_scope("main"), "<global>");
+        assert_eq!(name_scope("::main"), "<global>");
+        assert_eq!(name_scope("ns::Foo::bar"), "ns::Foo");
+        assert_eq!(name_scope("std::vector<std::pair<int,int> >::push_back"), "std::vector<std::pair<int,int> >");
+        assert_eq!(name_scope("Foo<a::b>"), "<global>");
+        assert_eq!(name_scope("Foo::operator<"), "Foo");
+        assert_eq!(name_scope("Foo::operator->"), "Foo");
+    }
 }